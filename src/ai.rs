@@ -0,0 +1,285 @@
+//! Depth-limited expectimax solver for the bitboard `Grid`.
+//!
+//! A max node tries the four directions and keeps the best-scoring child; a
+//! chance node enumerates every empty cell and branches on spawning a `2`
+//! (probability 0.9) or a `4` (probability 0.1), each weighted by 1/(number
+//! of empty cells). Leaf boards are scored with a weighted heuristic and the
+//! per-chance-node expectation is cached by raw bitboard so repeated
+//! transpositions within one search are only evaluated once.
+
+use crate::{apply_direction, Bitboard, Direction};
+use std::collections::HashMap;
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Left,
+    Direction::Right,
+    Direction::Up,
+    Direction::Down,
+];
+
+// Weights tuned the way most public bitboard 2048 solvers do: reward empty
+// cells and monotonic rows/columns, lightly penalize roughness between
+// neighbors, and favor keeping the largest tile cornered.
+const EMPTY_WEIGHT: f64 = 2.7;
+const MONOTONICITY_WEIGHT: f64 = 1.0;
+const SMOOTHNESS_WEIGHT: f64 = 0.1;
+const CORNER_WEIGHT: f64 = 2.0;
+
+/// Returns the direction expectimax rates highest, or `None` if no move
+/// changes the board (i.e. the game is over).
+pub fn best_move(board: Bitboard) -> Option<Direction> {
+    let depth = adaptive_depth(count_empty(board));
+    let mut cache = HashMap::new();
+
+    DIRECTIONS
+        .iter()
+        .copied()
+        .filter_map(|direction| {
+            let result = apply_direction(board, direction);
+
+            if result.board == board {
+                return None;
+            }
+
+            let value = chance_value(result.board, depth, &mut cache);
+
+            Some((direction, value))
+        })
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(direction, _)| direction)
+}
+
+fn max_value(board: Bitboard, depth: u32, cache: &mut HashMap<Bitboard, f64>) -> f64 {
+    let mut best = f64::NEG_INFINITY;
+    let mut has_move = false;
+
+    for &direction in DIRECTIONS.iter() {
+        let result = apply_direction(board, direction);
+
+        if result.board == board {
+            continue;
+        }
+
+        has_move = true;
+        best = best.max(chance_value(result.board, depth, cache));
+    }
+
+    if has_move {
+        best
+    } else {
+        heuristic(board)
+    }
+}
+
+fn chance_value(board: Bitboard, depth: u32, cache: &mut HashMap<Bitboard, f64>) -> f64 {
+    if let Some(&value) = cache.get(&board) {
+        return value;
+    }
+
+    let value = if depth == 0 {
+        heuristic(board)
+    } else {
+        let empties: Vec<usize> = (0..16).filter(|&i| exponent_at(board, i) == 0).collect();
+
+        if empties.is_empty() {
+            max_value(board, depth - 1, cache)
+        } else {
+            let weight = 1.0 / empties.len() as f64;
+
+            empties
+                .iter()
+                .map(|&index| {
+                    let spawn_2 = board | (1u64 << (index * 4));
+                    let spawn_4 = board | (2u64 << (index * 4));
+
+                    weight
+                        * (0.9 * max_value(spawn_2, depth - 1, cache)
+                            + 0.1 * max_value(spawn_4, depth - 1, cache))
+                })
+                .sum()
+        }
+    };
+
+    cache.insert(board, value);
+    value
+}
+
+/// Search deeper once the board is crowded and the branching factor of the
+/// chance nodes (one per empty cell) has shrunk.
+fn adaptive_depth(empty_count: u32) -> u32 {
+    match empty_count {
+        0 | 1 => 6,
+        2 | 3 => 5,
+        4 | 5 => 4,
+        6 | 7 => 3,
+        _ => 2,
+    }
+}
+
+fn exponent_at(board: Bitboard, index: usize) -> u8 {
+    ((board >> (index * 4)) & 0xF) as u8
+}
+
+fn exponents(board: Bitboard) -> [i32; 16] {
+    let mut values = [0i32; 16];
+
+    for (index, value) in values.iter_mut().enumerate() {
+        *value = exponent_at(board, index) as i32;
+    }
+
+    values
+}
+
+fn count_empty(board: Bitboard) -> u32 {
+    exponents(board).iter().filter(|&&exponent| exponent == 0).count() as u32
+}
+
+fn heuristic(board: Bitboard) -> f64 {
+    let values = exponents(board);
+
+    let empty = values.iter().filter(|&&exponent| exponent == 0).count() as f64;
+    let corner = if max_tile_in_corner(&values) { 1.0 } else { 0.0 };
+
+    EMPTY_WEIGHT * empty
+        + MONOTONICITY_WEIGHT * monotonicity_score(&values)
+        + SMOOTHNESS_WEIGHT * smoothness_score(&values)
+        + CORNER_WEIGHT * corner
+}
+
+/// Rewards boards where every row (and every column) trends consistently in
+/// one direction, by only penalizing whichever of the two directions is less
+/// monotonic — a perfectly monotonic row/column contributes no penalty.
+fn monotonicity_score(values: &[i32; 16]) -> f64 {
+    let mut penalty = 0.0;
+
+    for row in 0..4 {
+        let mut ascending = 0.0;
+        let mut descending = 0.0;
+
+        for col in 0..3 {
+            let current = values[row * 4 + col] as f64;
+            let next = values[row * 4 + col + 1] as f64;
+
+            if current > next {
+                descending += current - next;
+            } else {
+                ascending += next - current;
+            }
+        }
+
+        penalty += ascending.min(descending);
+    }
+
+    for col in 0..4 {
+        let mut ascending = 0.0;
+        let mut descending = 0.0;
+
+        for row in 0..3 {
+            let current = values[row * 4 + col] as f64;
+            let next = values[(row + 1) * 4 + col] as f64;
+
+            if current > next {
+                descending += current - next;
+            } else {
+                ascending += next - current;
+            }
+        }
+
+        penalty += ascending.min(descending);
+    }
+
+    -penalty
+}
+
+/// Penalizes large exponent gaps between neighboring tiles.
+fn smoothness_score(values: &[i32; 16]) -> f64 {
+    let mut penalty = 0.0;
+
+    for row in 0..4 {
+        for col in 0..3 {
+            let a = values[row * 4 + col];
+            let b = values[row * 4 + col + 1];
+
+            if a != 0 && b != 0 {
+                penalty += (a - b).abs() as f64;
+            }
+        }
+    }
+
+    for col in 0..4 {
+        for row in 0..3 {
+            let a = values[row * 4 + col];
+            let b = values[(row + 1) * 4 + col];
+
+            if a != 0 && b != 0 {
+                penalty += (a - b).abs() as f64;
+            }
+        }
+    }
+
+    -penalty
+}
+
+fn max_tile_in_corner(values: &[i32; 16]) -> bool {
+    let max_exponent = values.iter().copied().max().unwrap_or(0);
+
+    [0, 3, 12, 15]
+        .iter()
+        .any(|&corner| values[corner] == max_exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_board(exponents: [u8; 16]) -> Bitboard {
+        let mut board: Bitboard = 0;
+
+        for (index, &exponent) in exponents.iter().enumerate() {
+            board |= (exponent as u64) << (index * 4);
+        }
+
+        board
+    }
+
+    #[test]
+    fn it_doesnt_penalize_a_snaking_board() {
+        #[rustfmt::skip]
+        let values = [
+            4, 3, 2, 1,
+            5, 6, 7, 8,
+            12, 11, 10, 9,
+            13, 14, 15, 16,
+        ];
+
+        assert_eq!(monotonicity_score(&values), 0.0);
+    }
+
+    #[test]
+    fn it_returns_none_when_the_board_is_fully_locked() {
+        #[rustfmt::skip]
+        let board = make_board([
+            1, 2, 1, 2,
+            2, 1, 2, 1,
+            1, 2, 1, 2,
+            2, 1, 2, 1,
+        ]);
+
+        assert_eq!(best_move(board), None);
+    }
+
+    #[test]
+    fn it_returns_a_direction_that_actually_changes_the_board() {
+        #[rustfmt::skip]
+        let board = make_board([
+            1, 1, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0,
+        ]);
+
+        let direction = best_move(board).expect("a move should be available");
+
+        assert_ne!(apply_direction(board, direction).board, board);
+    }
+}
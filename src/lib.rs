@@ -1,9 +1,13 @@
 #![recursion_limit = "256"]
 
+mod ai;
+
+use rand::rngs::StdRng;
 use rand::seq::IteratorRandom;
-use rand::thread_rng;
-use rand::{rngs::ThreadRng, Rng};
+use rand::{thread_rng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use std::ops::{Add, AddAssign};
+use std::sync::OnceLock;
 use wasm_bindgen::prelude::*;
 use yew::prelude::*;
 use yew::services::keyboard::{KeyListenerHandle, KeyboardService};
@@ -28,15 +32,15 @@ impl Direction {
         }
     }
 
-    fn build_traversal(self) -> Vec<Position> {
+    fn build_traversal(self, size: usize) -> Vec<Position> {
         let i_traversal: Vec<usize> = match self {
-            Direction::Down => (0..4).rev().collect(),
-            _ => (0..4).collect(),
+            Direction::Down => (0..size).rev().collect(),
+            _ => (0..size).collect(),
         };
 
         let j_traversal: Vec<usize> = match self {
-            Direction::Right => (0..4).rev().collect(),
-            _ => (0..4).collect(),
+            Direction::Right => (0..size).rev().collect(),
+            _ => (0..size).collect(),
         };
 
         i_traversal
@@ -46,7 +50,7 @@ impl Direction {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 struct Position {
     i: usize,
     j: usize,
@@ -57,19 +61,19 @@ impl Position {
         Position { i, j }
     }
 
-    pub fn from_index(index: usize) -> Position {
+    pub fn from_index(index: usize, size: usize) -> Position {
         Position {
-            i: index / 4,
-            j: index % 4,
+            i: index / size,
+            j: index % size,
         }
     }
 
-    pub fn index(self) -> usize {
-        self.i * 4 + self.j
+    pub fn index(self, size: usize) -> usize {
+        self.i * size + self.j
     }
 
-    pub fn is_out_of_bounds(self) -> bool {
-        self.i >= 4 || self.j >= 4
+    pub fn is_out_of_bounds(self, size: usize) -> bool {
+        self.i >= size || self.j >= size
     }
 }
 
@@ -92,14 +96,14 @@ impl AddAssign<Direction> for Position {
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq)]
+#[derive(Debug, Copy, Clone, Eq, Serialize, Deserialize)]
 struct Tile {
     number: i32,
     state: TileState,
     previous_position: Option<Position>,
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 enum TileState {
     New,
     Static,
@@ -132,114 +136,597 @@ impl PartialEq for Tile {
     }
 }
 
-type Cell = Option<Tile>;
+/// A board packed into a `u64` as sixteen 4-bit nibbles, one per cell, laid
+/// out row-major (cell `index` occupies bits `[index*4, index*4+4)`). Each
+/// nibble holds the tile's exponent: 0 is empty, 1 is a `2` tile, 11 is a
+/// `2048` tile, and so on. Unlike `[Cell; 16]` this is `Copy` and hashable,
+/// which the AI's transposition table relies on.
+type Bitboard = u64;
+
+const ROW_MASK: u64 = 0xFFFF;
+
+/// The outcome of sliding+merging a single 16-bit row (four nibbles) toward
+/// its low nibble.
+#[derive(Debug, Clone, Copy)]
+struct RowResult {
+    packed: u16,
+    /// Bit `i` is set when output nibble `i` is the product of a merge, so
+    /// `tiles()` can render the "new tile doubled" pop animation.
+    merged_mask: u8,
+    score: u32,
+    /// For each output nibble, the input nibble (0-3) its tile came from.
+    /// Only meaningful where the corresponding `packed` nibble is nonzero;
+    /// lets `tiles()` animate a slide instead of teleporting tiles in place.
+    sources: [u8; 4],
+}
+
+fn compute_row_result(row: u16) -> RowResult {
+    let tiles: Vec<(u8, u8)> = (0..4)
+        .map(|i| (i as u8, ((row >> (i * 4)) & 0xF) as u8))
+        .filter(|&(_, exponent)| exponent != 0)
+        .collect();
+
+    let mut out = [0u8; 4];
+    let mut sources = [0u8; 4];
+    let mut merged_mask = 0u8;
+    let mut score = 0u32;
+    let mut out_index = 0;
+    let mut i = 0;
+
+    while i < tiles.len() {
+        let (origin, exponent) = tiles[i];
+
+        if i + 1 < tiles.len() && tiles[i + 1].1 == exponent {
+            debug_assert!(exponent < 15, "merged exponent would overflow its 4-bit nibble");
+
+            let merged_exponent = exponent + 1;
+            out[out_index] = merged_exponent;
+            sources[out_index] = origin;
+            merged_mask |= 1 << out_index;
+            score += 1 << merged_exponent;
+            i += 2;
+        } else {
+            out[out_index] = exponent;
+            sources[out_index] = origin;
+            i += 1;
+        }
+
+        out_index += 1;
+    }
+
+    let packed = out
+        .iter()
+        .enumerate()
+        .fold(0u16, |packed, (i, &exponent)| packed | ((exponent as u16) << (i * 4)));
 
-#[derive(Debug, Copy, Clone)]
+    RowResult {
+        packed,
+        merged_mask,
+        score,
+        sources,
+    }
+}
+
+/// Lookup table indexed by every possible packed row, giving the result of
+/// sliding that row toward its low nibble. Built once on first use; `Right`
+/// reuses it against bit-reversed rows instead of keeping a second table.
+fn left_table() -> &'static [RowResult] {
+    static TABLE: OnceLock<Box<[RowResult]>> = OnceLock::new();
+
+    TABLE.get_or_init(|| {
+        (0..=u16::MAX)
+            .map(compute_row_result)
+            .collect::<Vec<_>>()
+            .into_boxed_slice()
+    })
+}
+
+fn row_at(board: Bitboard, row: usize) -> u16 {
+    ((board >> (row * 16)) & ROW_MASK) as u16
+}
+
+fn set_row(board: Bitboard, row: usize, value: u16) -> Bitboard {
+    let cleared = board & !(ROW_MASK << (row * 16));
+    cleared | ((value as u64) << (row * 16))
+}
+
+fn reverse_row_nibbles(row: u16) -> u16 {
+    (0..4).fold(0u16, |out, i| out | (((row >> (i * 4)) & 0xF) << ((3 - i) * 4)))
+}
+
+fn reverse_mask_nibbles(mask: u8) -> u8 {
+    (0..4).fold(0u8, |out, i| {
+        out | (((mask >> i) & 1) << (3 - i))
+    })
+}
+
+/// Mirrors a `RowResult.sources` array the same way `reverse_row_nibbles`
+/// mirrors a row: both the output slot and the origin value it names flip
+/// around the row's center, since `slide_rows_right` looks up the table
+/// against a bit-reversed row.
+fn reverse_sources(sources: [u8; 4]) -> [u8; 4] {
+    let mut out = [0u8; 4];
+
+    for (i, &origin) in sources.iter().enumerate() {
+        out[3 - i] = 3 - origin;
+    }
+
+    out
+}
+
+/// Swaps rows and columns of a packed board using the nibble-swap bit trick:
+/// two rounds of masked shifts, the first swapping single nibbles across the
+/// diagonal of each 2x2 block, the second exchanging the two off-diagonal
+/// 2x2 blocks.
+fn transpose(board: Bitboard) -> Bitboard {
+    let a1 = board & 0xF0F00F0FF0F00F0F;
+    let a2 = board & 0x0000F0F00000F0F0;
+    let a3 = board & 0x0F0F00000F0F0000;
+    let a = a1 | (a2 << 12) | (a3 >> 12);
+
+    let b1 = a & 0xFF00FF0000FF00FF;
+    let b2 = a & 0x00FF00FF00000000;
+    let b3 = a & 0x00000000FF00FF00;
+
+    b1 | (b2 >> 24) | (b3 << 24)
+}
+
+fn transpose_mask(mask: u16) -> u16 {
+    (0..16).fold(0u16, |out, index| {
+        if mask & (1 << index) == 0 {
+            out
+        } else {
+            let (i, j) = (index / 4, index % 4);
+            out | (1 << (j * 4 + i))
+        }
+    })
+}
+
+struct MoveResult {
+    board: Bitboard,
+    merged_mask: u16,
+    score: u32,
+    /// Board index (0-15) each destination cell's tile slid from. Only
+    /// meaningful where that cell ended up nonzero.
+    sources: [u8; 16],
+}
+
+fn slide_rows_left(board: Bitboard) -> MoveResult {
+    let table = left_table();
+    let mut result = MoveResult {
+        board: 0,
+        merged_mask: 0,
+        score: 0,
+        sources: [0; 16],
+    };
+
+    for row in 0..4 {
+        let entry = table[row_at(board, row) as usize];
+        result.board = set_row(result.board, row, entry.packed);
+        result.merged_mask |= (entry.merged_mask as u16) << (row * 4);
+        result.score += entry.score;
+
+        for col in 0..4 {
+            result.sources[row * 4 + col] = (row * 4) as u8 + entry.sources[col];
+        }
+    }
+
+    result
+}
+
+fn slide_rows_right(board: Bitboard) -> MoveResult {
+    let table = left_table();
+    let mut result = MoveResult {
+        board: 0,
+        merged_mask: 0,
+        score: 0,
+        sources: [0; 16],
+    };
+
+    for row in 0..4 {
+        let entry = table[reverse_row_nibbles(row_at(board, row)) as usize];
+        result.board = set_row(result.board, row, reverse_row_nibbles(entry.packed));
+        result.merged_mask |= (reverse_mask_nibbles(entry.merged_mask) as u16) << (row * 4);
+        result.score += entry.score;
+
+        let sources = reverse_sources(entry.sources);
+        for col in 0..4 {
+            result.sources[row * 4 + col] = (row * 4) as u8 + sources[col];
+        }
+    }
+
+    result
+}
+
+/// Swaps a 4x4 board-index's row and column, the same permutation `transpose`
+/// applies to whole boards and `transpose_mask` applies to bitmasks.
+fn transpose_board_index(index: usize) -> usize {
+    (index % 4) * 4 + (index / 4)
+}
+
+fn transpose_move_result(result: MoveResult) -> MoveResult {
+    let mut sources = [0u8; 16];
+
+    for destination in 0..16 {
+        sources[transpose_board_index(destination)] =
+            transpose_board_index(result.sources[destination] as usize) as u8;
+    }
+
+    MoveResult {
+        board: transpose(result.board),
+        merged_mask: transpose_mask(result.merged_mask),
+        score: result.score,
+        sources,
+    }
+}
+
+fn apply_direction(board: Bitboard, direction: Direction) -> MoveResult {
+    match direction {
+        Direction::Left => slide_rows_left(board),
+        Direction::Right => slide_rows_right(board),
+        Direction::Up => transpose_move_result(slide_rows_left(transpose(board))),
+        Direction::Down => transpose_move_result(slide_rows_right(transpose(board))),
+    }
+}
+
+/// How a `Grid`'s cells are stored. The bitboard row tables above only make
+/// sense for the classic 4x4 board (a row has to fit in 16 bits); any other
+/// board size falls back to one exponent byte per cell, moved with a plain
+/// per-line slide+merge since there's no fixed board width to build a
+/// lookup table around.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Storage {
+    Packed(Bitboard),
+    Generic(Vec<u8>),
+}
+
+/// The part of a `Grid`'s state worth stepping backward/forward through. The
+/// animation masks and rng are deliberately left out: undoing a move should
+/// restore the board, not replay which cells happened to pop last frame.
+#[derive(Debug, Clone)]
+struct GridSnapshot {
+    size: usize,
+    storage: Storage,
+    score: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Grid {
-    cells: [Cell; 16],
-    rng: ThreadRng,
+    size: usize,
+    storage: Storage,
+    /// Seed a fresh per-draw RNG is derived from, so a reloaded grid keeps
+    /// drawing from the same random sequence instead of restarting it.
+    seed: u64,
+    /// How many random-tile draws have happened so far. Combined with
+    /// `seed` to derive each draw's RNG, since `StdRng` itself can't be
+    /// serialized.
+    draws: u64,
     enable_new_tiles: bool,
+    /// Sum of the value of every tile produced by a merge so far.
+    score: u32,
+    /// Cells spawned since the last move, for rendering the "new tile" pop.
+    #[serde(skip)]
+    new_mask: u64,
+    /// Cells produced by a merge on the last move, for the merge-pop ghost
+    /// tile rendered alongside them in `tiles()`.
+    #[serde(skip)]
+    merged_mask: u64,
+    /// Board index each cell's tile slid from on the last move, indexed by
+    /// destination cell; `None` where it didn't move. Lets `tiles()`
+    /// animate a slide instead of teleporting.
+    #[serde(skip)]
+    previous_positions: Vec<Option<usize>>,
+    /// Snapshots taken before each successful move, most recent last.
+    #[serde(skip)]
+    undo_stack: Vec<GridSnapshot>,
+    /// Snapshots undone off `undo_stack`, popped back off as moves are
+    /// redone. Cleared whenever a fresh move diverges from history.
+    #[serde(skip)]
+    redo_stack: Vec<GridSnapshot>,
 }
 
 impl Default for Grid {
     fn default() -> Self {
-        let mut grid = Grid::new([None; 16]);
-
-        for _ in 0..2 {
-            grid.add_random_tile();
-        }
-
-        grid
+        Grid::sized(4)
     }
 }
 
 impl PartialEq for Grid {
     fn eq(&self, other: &Grid) -> bool {
-        self.cells == other.cells
+        self.size == other.size
+            && match (&self.storage, &other.storage) {
+                (Storage::Packed(a), Storage::Packed(b)) => a == b,
+                (Storage::Generic(a), Storage::Generic(b)) => a == b,
+                _ => false,
+            }
     }
 }
 
 impl Grid {
-    pub fn new(cells: [Cell; 16]) -> Grid {
+    /// Panics if `size` is outside the `3..=8` range the engine supports —
+    /// `new_mask`/`merged_mask` pack one bit per cell into a `u64`, so a
+    /// larger board would silently wrap its bit shifts instead of failing
+    /// loudly.
+    pub fn new(size: usize) -> Grid {
+        assert!(
+            (3..=8).contains(&size),
+            "grid size must be between 3 and 8, got {}",
+            size
+        );
+
+        let storage = if size == 4 {
+            Storage::Packed(0)
+        } else {
+            Storage::Generic(vec![0; size * size])
+        };
+
+        let seed = thread_rng().gen();
+
         Grid {
-            cells,
-            rng: thread_rng(),
+            size,
+            storage,
+            seed,
+            draws: 0,
             enable_new_tiles: true,
+            score: 0,
+            new_mask: 0,
+            merged_mask: 0,
+            previous_positions: vec![None; size * size],
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
         }
     }
 
+    pub fn sized(size: usize) -> Grid {
+        let mut grid = Grid::new(size);
+
+        for _ in 0..2 {
+            grid.add_random_tile();
+        }
+
+        grid
+    }
+
     pub fn disable_new_tiles(&mut self) {
         self.enable_new_tiles = false;
     }
 
-    fn get(&self, position: Position) -> Option<Tile> {
-        self.cells.get(position.index()).and_then(|tile| *tile)
+    pub fn size(&self) -> usize {
+        self.size
     }
 
-    fn prepare_for_move(&mut self) {
-        for i in 0..16 {
-            self.cells
-                .get_mut(i)
-                .and_then(|cell| cell.as_mut())
-                .map(|tile| {
-                    tile.state = TileState::Static;
-                    tile.previous_position = Some(Position::from_index(i));
-                });
+    /// The packed bitboard, when this grid is the classic 4x4 size the AI
+    /// solver understands.
+    pub fn board(&self) -> Option<Bitboard> {
+        match &self.storage {
+            Storage::Packed(board) => Some(*board),
+            Storage::Generic(_) => None,
         }
     }
 
-    pub fn move_in(&mut self, direction: Direction) {
-        self.prepare_for_move();
+    pub fn score(&self) -> u32 {
+        self.score
+    }
 
-        let traversal = direction.build_traversal();
+    /// Whether any tile has reached 2048 (exponent 11). Keeps returning
+    /// `true` after the player continues past the win, so the caller decides
+    /// whether to keep showing a banner.
+    pub fn has_won(&self) -> bool {
+        let total_cells = self.size * self.size;
 
-        let mut moved = false;
+        (0..total_cells).any(|index| self.exponent_at(index) >= 11)
+    }
 
-        for start_position in traversal {
-            moved |= self.traverse_from(start_position, direction);
+    /// Whether any move would change the board: there's an empty cell, or
+    /// two equal tiles sit next to each other in a row or column.
+    pub fn can_move(&self) -> bool {
+        let size = self.size;
+
+        if (0..size * size).any(|index| self.exponent_at(index) == 0) {
+            return true;
         }
 
-        if moved {
-            self.add_random_tile()
+        for i in 0..size {
+            for j in 0..size {
+                let exponent = self.exponent_at(Position::new(i, j).index(size));
+
+                let matches_right = j + 1 < size
+                    && self.exponent_at(Position::new(i, j + 1).index(size)) == exponent;
+                let matches_down = i + 1 < size
+                    && self.exponent_at(Position::new(i + 1, j).index(size)) == exponent;
+
+                if matches_right || matches_down {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        !self.can_move()
+    }
+
+    fn exponent_at(&self, index: usize) -> u8 {
+        match &self.storage {
+            Storage::Packed(board) => ((board >> (index * 4)) & 0xF) as u8,
+            Storage::Generic(cells) => cells[index],
+        }
+    }
+
+    fn set_exponent_at(&mut self, index: usize, exponent: u8) {
+        match &mut self.storage {
+            Storage::Packed(board) => {
+                *board &= !(0xFu64 << (index * 4));
+                *board |= (exponent as u64) << (index * 4);
+            }
+            Storage::Generic(cells) => cells[index] = exponent,
         }
     }
 
-    fn traverse_from(&mut self, start_position: Position, in_direction: Direction) -> bool {
-        let mut start_tile = match self.get(start_position) {
-            Some(tile) => tile,
-            None => return false,
+    pub fn move_in(&mut self, direction: Direction) -> bool {
+        self.new_mask = 0;
+        self.merged_mask = 0;
+        self.previous_positions = vec![None; self.size * self.size];
+
+        let before = self.snapshot();
+
+        let moved = match &mut self.storage {
+            Storage::Packed(board) => {
+                let result = apply_direction(*board, direction);
+
+                if result.board == *board {
+                    false
+                } else {
+                    *board = result.board;
+                    self.merged_mask = result.merged_mask as u64;
+                    self.score += result.score;
+
+                    for destination in 0..16 {
+                        let origin = result.sources[destination] as usize;
+                        self.previous_positions[destination] =
+                            if origin != destination { Some(origin) } else { None };
+                    }
+
+                    true
+                }
+            }
+            Storage::Generic(_) => self.move_generic(direction),
         };
 
-        let mut new_position = start_position;
+        if moved {
+            self.undo_stack.push(before);
+            self.redo_stack.clear();
+            self.add_random_tile();
+        }
+
+        moved
+    }
+
+    fn snapshot(&self) -> GridSnapshot {
+        GridSnapshot {
+            size: self.size,
+            storage: self.storage.clone(),
+            score: self.score,
+        }
+    }
+
+    fn restore(&mut self, snapshot: GridSnapshot) {
+        self.size = snapshot.size;
+        self.storage = snapshot.storage;
+        self.score = snapshot.score;
+        self.new_mask = 0;
+        self.merged_mask = 0;
+        self.previous_positions = vec![None; self.size * self.size];
+    }
+
+    /// Pops the most recent move off the undo stack and restores the board
+    /// to how it looked beforehand, pushing the current state onto the redo
+    /// stack. Returns `false` if there's nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(snapshot) => {
+                self.redo_stack.push(self.snapshot());
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Re-applies the most recently undone move. Returns `false` if there's
+    /// nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(snapshot) => {
+                self.undo_stack.push(self.snapshot());
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Serializes the board (size, storage, and rng seed) to a JSON string
+    /// suitable for localStorage or a shareable link.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Grid always serializes")
+    }
+
+    /// Restores a `Grid` from JSON produced by `to_json`. The next
+    /// random-tile draw picks up from `draws`, so play continues with the
+    /// same random sequence it would have had without the round trip.
+    pub fn from_json(json: &str) -> Result<Grid, serde_json::Error> {
+        let mut grid: Grid = serde_json::from_str(json)?;
+        grid.previous_positions = vec![None; grid.size * grid.size];
+
+        Ok(grid)
+    }
+
+    fn move_generic(&mut self, direction: Direction) -> bool {
+        let size = self.size;
+        let mut moved = false;
+
+        for start_position in direction.build_traversal(size) {
+            moved |= self.traverse_from_generic(start_position, direction);
+        }
+
+        moved
+    }
+
+    fn traverse_from_generic(&mut self, start_position: Position, direction: Direction) -> bool {
+        let size = self.size;
+        let start_index = start_position.index(size);
+        let mut exponent = self.exponent_at(start_index);
+
+        if exponent == 0 {
+            return false;
+        }
+
+        let mut current_position = start_position;
+        let mut merged = false;
 
         loop {
-            let next_position = new_position + in_direction;
+            let next_position = current_position + direction;
 
-            if next_position.is_out_of_bounds() {
+            if next_position.is_out_of_bounds(size) {
                 break;
             }
 
-            if let Some(tile) = self.get(next_position) {
-                if tile == start_tile && tile.state != TileState::Merged {
-                    start_tile.number *= 2;
-                    start_tile.state = TileState::Merged;
-                    new_position = next_position;
-                }
+            let next_index = next_position.index(size);
+            let next_exponent = self.exponent_at(next_index);
 
-                break;
+            if next_exponent == 0 {
+                current_position = next_position;
+                continue;
+            }
+
+            if !merged && next_exponent == exponent && self.merged_mask & (1 << next_index) == 0 {
+                exponent += 1;
+                merged = true;
+                current_position = next_position;
             }
 
-            new_position = next_position;
+            break;
         }
 
-        if start_position == new_position {
+        if current_position == start_position {
             return false;
         }
 
-        self.cells[start_position.index()] = None;
-        self.cells[new_position.index()] = Some(start_tile);
+        let destination_index = current_position.index(size);
 
-        return true;
+        self.set_exponent_at(start_index, 0);
+        self.set_exponent_at(destination_index, exponent);
+        self.previous_positions[destination_index] = Some(start_index);
+
+        if merged {
+            self.merged_mask |= 1 << destination_index;
+            self.score += 1 << exponent;
+        }
+
+        true
     }
 
     fn add_random_tile(&mut self) {
@@ -247,49 +734,95 @@ impl Grid {
             return;
         }
 
-        let rng = &mut self.rng;
+        let total_cells = self.size * self.size;
+        let empty_indices: Vec<usize> = (0..total_cells)
+            .filter(|&index| self.exponent_at(index) == 0)
+            .collect();
 
-        let empty_cells = self.cells.iter_mut().filter(|x| x.is_none());
+        if empty_indices.is_empty() {
+            return;
+        }
 
-        if let Some(empty) = empty_cells.choose(rng) {
-            let number = match self.rng.gen::<f64>() {
-                x if x > 0.9 => 4,
-                _ => 2,
+        let mut rng = self.draw_rng();
+
+        if let Some(&index) = empty_indices.iter().choose(&mut rng) {
+            let exponent = match rng.gen::<f64>() {
+                x if x > 0.9 => 2,
+                _ => 1,
             };
 
-            *empty = Some(Tile::new(number));
+            self.set_exponent_at(index, exponent);
+            self.new_mask |= 1 << index;
         }
     }
 
+    /// Derives the RNG for the next random-tile draw from `seed` and
+    /// `draws`, then advances the counter. `StdRng` isn't itself
+    /// serializable, so each draw gets its own independently-seeded RNG
+    /// rather than one long-lived stream, keeping `to_json`/`from_json`
+    /// round trips resumable.
+    fn draw_rng(&mut self) -> StdRng {
+        let draw_seed = self.seed.wrapping_add(self.draws.wrapping_mul(0x9E3779B97F4A7C15));
+        self.draws += 1;
+
+        StdRng::seed_from_u64(draw_seed)
+    }
+
     fn tiles(&self) -> impl Iterator<Item = (Position, Tile)> + '_ {
-        self.cells
-            .iter()
-            .enumerate()
-            .filter_map(|(i, cell)| match cell {
-                None => None,
-                Some(tile) => Some((Position::from_index(i), *tile)),
-            })
-            .flat_map(|(position, tile)| match tile.state {
-                TileState::Merged => vec![
-                    (position, tile),
-                    (
+        let total_cells = self.size * self.size;
+
+        (0..total_cells)
+            .filter_map(move |index| {
+                let exponent = self.exponent_at(index);
+
+                if exponent == 0 {
+                    return None;
+                }
+
+                let position = Position::from_index(index, self.size);
+                let number = 1 << exponent;
+                let previous_position = self.previous_positions[index]
+                    .map(|origin| Position::from_index(origin, self.size));
+
+                if self.new_mask & (1 << index) != 0 {
+                    Some(vec![(position, Tile::new(number))])
+                } else if self.merged_mask & (1 << index) != 0 {
+                    Some(vec![
+                        (
+                            position,
+                            Tile {
+                                number,
+                                state: TileState::Merged,
+                                previous_position: None,
+                            },
+                        ),
+                        (
+                            position,
+                            Tile {
+                                number: number / 2,
+                                state: TileState::Static,
+                                previous_position,
+                            },
+                        ),
+                    ])
+                } else {
+                    Some(vec![(
                         position,
                         Tile {
+                            number,
                             state: TileState::Static,
-                            previous_position: tile.previous_position,
-                            number: tile.number / 2,
+                            previous_position,
                         },
-                    ),
-                ],
-                _ => vec![(position, tile)],
+                    )])
+                }
             })
+            .flatten()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Direction, Grid, Tile};
-    use std::convert::TryInto;
+    use crate::{Direction, Grid};
 
     #[test]
     fn it_works() {
@@ -453,20 +986,16 @@ mod tests {
     }
 
     fn make_grid(from_numbers: [i32; 16]) -> Grid {
-        Grid::new(
-            from_numbers
-                .iter()
-                .map(|number| {
-                    if *number > 0 {
-                        Some(Tile::new(*number))
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<Option<Tile>>>()
-                .try_into()
-                .unwrap(),
-        )
+        let mut grid = Grid::new(4);
+
+        for (index, &number) in from_numbers.iter().enumerate() {
+            if number > 0 {
+                let exponent = (number as f64).log2().round() as u8;
+                grid.set_exponent_at(index, exponent);
+            }
+        }
+
+        grid
     }
 
     #[test]
@@ -481,7 +1010,7 @@ mod tests {
 
         grid.move_in(Direction::Right);
 
-        let count = grid.cells.iter().filter(|cell| cell.is_some()).count();
+        let count = (0..16).filter(|&index| grid.exponent_at(index) != 0).count();
 
         assert_eq!(2, count);
     }
@@ -498,10 +1027,158 @@ mod tests {
 
         grid.move_in(Direction::Right);
 
-        let count = grid.cells.iter().filter(|cell| cell.is_some()).count();
+        let count = (0..16).filter(|&index| grid.exponent_at(index) != 0).count();
 
         assert_eq!(1, count);
     }
+
+    #[test]
+    fn it_detects_a_win() {
+        #[rustfmt::skip]
+        let grid = make_grid([
+            2048, 0, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0,
+        ]);
+
+        assert!(grid.has_won());
+    }
+
+    #[test]
+    fn it_detects_game_over_on_a_full_board_with_no_legal_moves() {
+        #[rustfmt::skip]
+        let grid = make_grid([
+            2, 4, 2, 4,
+            4, 2, 4, 2,
+            2, 4, 2, 4,
+            4, 2, 4, 2,
+        ]);
+
+        assert!(!grid.can_move());
+        assert!(grid.is_game_over());
+    }
+
+    #[test]
+    fn it_doesnt_detect_game_over_when_a_full_board_has_a_legal_merge() {
+        #[rustfmt::skip]
+        let grid = make_grid([
+            2, 4, 2, 4,
+            4, 2, 4, 2,
+            2, 4, 2, 2,
+            4, 2, 4, 2,
+        ]);
+
+        assert!(grid.can_move());
+        assert!(!grid.is_game_over());
+    }
+
+    #[test]
+    fn it_round_trips_through_json_with_the_same_rng_sequence() {
+        #[rustfmt::skip]
+        let mut grid = make_grid([
+            2, 0, 4, 0,
+            0, 0, 0, 0,
+            2, 0, 4, 0,
+            0, 0, 0, 0,
+        ]);
+
+        for direction in [Direction::Left, Direction::Up, Direction::Left, Direction::Up] {
+            grid.move_in(direction);
+        }
+
+        assert!(grid.draws > 0, "setup should have drawn at least one tile");
+
+        let json = grid.to_json();
+        let mut restored = Grid::from_json(&json).expect("round trip should parse");
+
+        assert_eq!(grid, restored);
+        assert_eq!(grid.seed, restored.seed);
+        assert_eq!(grid.draws, restored.draws);
+        let grid_draws_before_continuation = restored.draws;
+
+        // The draw sequence must resume from where the save happened, not
+        // restart from the beginning: drawing further on both the original
+        // and the restored grid in lockstep has to keep producing the same
+        // tiles.
+        let mut original = grid;
+        for direction in [Direction::Right, Direction::Down, Direction::Right] {
+            original.move_in(direction);
+            restored.move_in(direction);
+
+            assert_eq!(original, restored);
+        }
+
+        assert!(original.draws > grid_draws_before_continuation);
+    }
+
+    #[test]
+    fn it_restores_score_on_undo_then_redo() {
+        #[rustfmt::skip]
+        let mut grid = make_grid([
+            2, 2, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0,
+            0, 0, 0, 0,
+        ]);
+        grid.disable_new_tiles();
+
+        grid.move_in(Direction::Left);
+        assert_eq!(grid.score(), 4);
+
+        assert!(grid.undo());
+        assert_eq!(grid.score(), 0);
+
+        assert!(grid.redo());
+        assert_eq!(grid.score(), 4);
+
+        assert!(!grid.redo());
+    }
+
+    #[test]
+    fn it_slides_and_merges_on_a_non_4x4_board() {
+        let mut grid = Grid::new(5);
+        grid.disable_new_tiles();
+
+        grid.set_exponent_at(0, 1); // row 0, col 0: a 2
+        grid.set_exponent_at(1, 1); // row 0, col 1: a 2
+
+        grid.move_in(Direction::Left);
+
+        assert_eq!(grid.exponent_at(0), 2);
+        assert_eq!(grid.exponent_at(1), 0);
+    }
+}
+
+/// Reads `?size=N` from the page URL, clamped to the 3x3..8x8 range the
+/// engine supports. Falls back to the classic 4x4 board.
+fn size_from_query() -> usize {
+    let search = yew::utils::window()
+        .location()
+        .search()
+        .unwrap_or_default();
+
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("size="))
+        .and_then(|value| value.parse::<usize>().ok())
+        .map(|size| size.clamp(3, 8))
+        .unwrap_or(4)
+}
+
+#[derive(Properties, Clone, PartialEq)]
+struct ModelProps {
+    #[prop_or_else(size_from_query)]
+    size: usize,
+}
+
+impl Default for ModelProps {
+    fn default() -> Self {
+        ModelProps {
+            size: size_from_query(),
+        }
+    }
 }
 
 struct Model {
@@ -510,23 +1187,64 @@ struct Model {
     #[allow(dead_code)]
     keyboard_event_listener: KeyListenerHandle,
     current_render: i32,
+    solving: bool,
+    #[allow(dead_code)]
+    solve_render_task: Option<RenderTask>,
+    best_score: u32,
+    /// Set once the player keeps playing past a win, so the win overlay
+    /// doesn't keep popping back up on every later move.
+    continued_after_win: bool,
 }
 
 impl Model {
     fn move_in(&mut self, direction: Direction) {
+        if self.grid.has_won() {
+            self.continued_after_win = true;
+        }
+
         self.grid.move_in(direction);
+        self.best_score = self.best_score.max(self.grid.score());
+    }
+
+    fn undo(&mut self) {
+        self.grid.undo();
+    }
+
+    fn redo(&mut self) {
+        self.grid.redo();
+    }
+
+    fn toggle_solving(&mut self) {
+        if self.grid.board().is_none() {
+            return;
+        }
+
+        self.solving = !self.solving;
+
+        if self.solving {
+            self.schedule_solve_tick();
+        }
+    }
+
+    fn schedule_solve_tick(&mut self) {
+        let link = self.link.clone();
+
+        self.solve_render_task = Some(RenderService::request_animation_frame(
+            link.callback(|_| Msg::SolveTick),
+        ));
     }
 }
 
 enum Msg {
     KeyboardEvent(KeyboardEvent),
+    SolveTick,
 }
 
 impl Component for Model {
     type Message = Msg;
-    type Properties = ();
+    type Properties = ModelProps;
 
-    fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
         let keyboard_event_listener = KeyboardService::register_key_down(
             &document(),
             (&link).callback(|e: KeyboardEvent| Msg::KeyboardEvent(e)),
@@ -534,9 +1252,13 @@ impl Component for Model {
 
         Self {
             link,
-            grid: Grid::default(),
+            grid: Grid::sized(props.size),
             current_render: 0,
             keyboard_event_listener,
+            solving: false,
+            solve_render_task: None,
+            best_score: 0,
+            continued_after_win: false,
         }
     }
 
@@ -547,8 +1269,24 @@ impl Component for Model {
                 38 => self.move_in(Direction::Up),
                 39 => self.move_in(Direction::Right),
                 40 => self.move_in(Direction::Down),
+                83 => self.toggle_solving(),
+                85 => self.undo(),
+                82 => self.redo(),
                 _ => return false,
             },
+            Msg::SolveTick => {
+                if !self.solving {
+                    return false;
+                }
+
+                match self.grid.board().and_then(ai::best_move) {
+                    Some(direction) => {
+                        self.move_in(direction);
+                        self.schedule_solve_tick();
+                    }
+                    None => self.solving = false,
+                }
+            }
         };
 
         self.current_render += 1;
@@ -561,11 +1299,31 @@ impl Component for Model {
     }
 
     fn view(&self) -> Html {
+        let wrapper_class = if self.solving {
+            "grid-wrapper solving"
+        } else {
+            "grid-wrapper"
+        };
+        let cell_count = self.grid.size() * self.grid.size();
+        let show_win_overlay = self.grid.has_won() && !self.continued_after_win;
+        let show_game_over_overlay = !show_win_overlay && self.grid.is_game_over();
+
         html! {
-            <div class="grid-wrapper">
+            <div class=wrapper_class>
+                <div class="scores">
+                    <div class="score">{ format!("Score: {}", self.grid.score()) }</div>
+                    <div class="best-score">{ format!("Best: {}", self.best_score) }</div>
+                </div>
                 <div class="grid" key=self.current_render>
-                { for (0..16).map(|_| { html! { <div class="cell"></div> }}) }
+                { for (0..cell_count).map(|_| { html! { <div class="cell"></div> }}) }
                 { for self.grid.tiles().map(|(position, tile)| html! { <TileComponent position=position tile=tile />} ) }
+                { if show_win_overlay {
+                    html! { <div class="overlay overlay-win">{ "You win!" }</div> }
+                } else if show_game_over_overlay {
+                    html! { <div class="overlay overlay-game-over">{ "Game over" }</div> }
+                } else {
+                    html! {}
+                } }
                 </div>
             </div>
         }
@@ -588,8 +1346,8 @@ impl TileComponent {
             } else {
                 "super".to_string()
             },
-            self.position.index() % 4,
-            self.position.index() / 4,
+            self.position.j,
+            self.position.i,
             self.tile.state.to_string(),
         )
     }